@@ -0,0 +1,368 @@
+use std::str::FromStr;
+
+use bitcoin::util::base58;
+use bitcoin::util::bip32::{ChildNumber, ExtendedPubKey, Fingerprint};
+use bitcoin::{Address, Network};
+
+use crate::types::ScriptType;
+use crate::util::{self, derive_child};
+
+/// Which of a wallet's two standard derivation branches an address belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    Receive,
+    Change,
+}
+
+/// The derivation index (or pair of indexes) a descriptor uses for its receive/change branches,
+/// parsed from the key expression's penultimate path element: a plain `0`/`1`, or the combined
+/// `<0;1>` multi-path syntax that lets one descriptor cover both chains.
+#[derive(Debug, Clone, Copy)]
+enum Chains {
+    Single(u32),
+    ReceiveChange(u32, u32),
+}
+
+impl Chains {
+    fn index_for(&self, chain: Chain) -> u32 {
+        match (self, chain) {
+            (Chains::Single(i), _) => *i,
+            (Chains::ReceiveChange(receive, _), Chain::Receive) => *receive,
+            (Chains::ReceiveChange(_, change), Chain::Change) => *change,
+        }
+    }
+}
+
+/// The `[fingerprint/derivation/path]` key origin info prefixing a descriptor's extended key.
+#[derive(Debug, Clone)]
+pub struct KeyOrigin {
+    pub fingerprint: Fingerprint,
+    pub path: Vec<ChildNumber>,
+}
+
+struct DescriptorKey {
+    origin: Option<KeyOrigin>,
+    extended_pubkey: ExtendedPubKey,
+    chains: Chains,
+}
+
+/// A parsed output descriptor, resolved down to the `ScriptType` and cosigner extended pubkeys
+/// needed to derive addresses — the same pipeline xyzpub/multisig imports feed into.
+pub struct Descriptor {
+    pub network: Network,
+    pub script_type: ScriptType,
+    pub threshold: Option<usize>,
+    keys: Vec<DescriptorKey>,
+}
+
+#[derive(Debug)]
+pub enum DescriptorError {
+    UnsupportedScript,
+    Invalid(&'static str),
+}
+
+impl Descriptor {
+    /// Mirrors `XyzPubKey::matches_network`/`XyzMultisig::matches_network`: rust-bitcoin's
+    /// `ExtendedPubKey::network` only ever resolves to `Bitcoin` or `Testnet` for xpub/tpub
+    /// strings (it has no Signet/Regtest version bytes to parse), so a tpub-based descriptor
+    /// has to be accepted for Regtest/Signet backends too, not just Testnet.
+    pub fn matches_network(&self, network: Network) -> bool {
+        self.network == network
+            || (self.network == Network::Testnet
+                && matches!(network, Network::Regtest | Network::Signet))
+    }
+
+    pub fn key_origins(&self) -> impl Iterator<Item = Option<&KeyOrigin>> {
+        self.keys.iter().map(|key| key.origin.as_ref())
+    }
+
+    pub fn derive_address(&self, chain: Chain, index: u32) -> Result<Address, base58::Error> {
+        let branches: Vec<ExtendedPubKey> = self
+            .keys
+            .iter()
+            .map(|key| derive_child(&key.extended_pubkey, key.chains.index_for(chain)))
+            .collect::<Result<_, _>>()?;
+
+        match self.threshold {
+            None => util::derive_address_for(self.network, self.script_type, &branches[0], index),
+            Some(threshold) => util::derive_multisig_address_for(
+                self.network,
+                self.script_type,
+                &branches,
+                threshold,
+                index,
+            ),
+        }
+    }
+}
+
+impl FromStr for Descriptor {
+    type Err = DescriptorError;
+
+    fn from_str(desc: &str) -> Result<Descriptor, DescriptorError> {
+        // strip the optional `#checksum` suffix
+        let desc = desc.split('#').next().unwrap().trim();
+
+        if let Some(inner) = strip_wrapper(desc, "wpkh(") {
+            Ok(single(ScriptType::P2wpkh, parse_key(inner)?))
+        } else if let Some(inner) = strip_wrapper(desc, "pkh(") {
+            Ok(single(ScriptType::P2pkh, parse_key(inner)?))
+        } else if let Some(inner) = strip_wrapper(desc, "tr(") {
+            Ok(single(ScriptType::P2tr, parse_key(inner)?))
+        } else if let Some(inner) = strip_wrapper(desc, "sh(wpkh(").and_then(|s| s.strip_suffix(')')) {
+            Ok(single(ScriptType::P2shP2wpkh, parse_key(inner)?))
+        } else if let Some(inner) = desc
+            .strip_prefix("wsh(sortedmulti(")
+            .and_then(|s| s.strip_suffix("))"))
+        {
+            parse_sortedmulti(inner, ScriptType::P2wsh)
+        } else if let Some(inner) = desc
+            .strip_prefix("sh(wsh(sortedmulti(")
+            .and_then(|s| s.strip_suffix(")))"))
+        {
+            parse_sortedmulti(inner, ScriptType::P2shP2wsh)
+        } else {
+            Err(DescriptorError::UnsupportedScript)
+        }
+    }
+}
+
+fn single(script_type: ScriptType, key: DescriptorKey) -> Descriptor {
+    let network = key.extended_pubkey.network;
+    Descriptor {
+        network,
+        script_type,
+        threshold: None,
+        keys: vec![key],
+    }
+}
+
+/// Strip a `func(...)` wrapper, returning the contents between the outermost matching parens.
+fn strip_wrapper<'a>(desc: &'a str, prefix: &str) -> Option<&'a str> {
+    let inner = desc.strip_prefix(prefix)?;
+    inner.strip_suffix(')')
+}
+
+fn parse_sortedmulti(inner: &str, script_type: ScriptType) -> Result<Descriptor, DescriptorError> {
+    let mut parts = inner.split(',');
+    let threshold: usize = parts
+        .next()
+        .ok_or(DescriptorError::Invalid("missing multisig threshold"))?
+        .parse()
+        .map_err(|_| DescriptorError::Invalid("invalid multisig threshold"))?;
+
+    let keys = parts.map(parse_key).collect::<Result<Vec<_>, _>>()?;
+    let network = keys
+        .first()
+        .ok_or(DescriptorError::Invalid("sortedmulti with no keys"))?
+        .extended_pubkey
+        .network;
+
+    if keys.iter().any(|key| key.extended_pubkey.network != network) {
+        return Err(DescriptorError::Invalid("sortedmulti cosigners on different networks"));
+    }
+    if threshold == 0 || threshold > keys.len() {
+        return Err(DescriptorError::Invalid("invalid multisig threshold"));
+    }
+
+    Ok(Descriptor {
+        network,
+        script_type,
+        threshold: Some(threshold),
+        keys,
+    })
+}
+
+/// Parse a single key expression: `[fingerprint/path]xpub.../chain/*`.
+fn parse_key(expr: &str) -> Result<DescriptorKey, DescriptorError> {
+    let (origin, rest) = if let Some(stripped) = expr.strip_prefix('[') {
+        let (origin_str, rest) = stripped
+            .split_once(']')
+            .ok_or(DescriptorError::Invalid("unterminated key origin"))?;
+        (Some(parse_origin(origin_str)?), rest)
+    } else {
+        (None, expr)
+    };
+
+    let mut components = rest.split('/');
+    let xpub = components
+        .next()
+        .ok_or(DescriptorError::Invalid("missing extended key"))?;
+    let extended_pubkey = ExtendedPubKey::from_str(xpub)
+        .map_err(|_| DescriptorError::Invalid("invalid extended pubkey"))?;
+
+    let chain_component = components
+        .next()
+        .ok_or(DescriptorError::Invalid("missing ranged derivation path"))?;
+    if components.next() != Some("*") {
+        return Err(DescriptorError::Invalid("expected a ranged `*` path element"));
+    }
+
+    let chains = parse_chains(chain_component)?;
+
+    Ok(DescriptorKey {
+        origin,
+        extended_pubkey,
+        chains,
+    })
+}
+
+fn parse_origin(origin: &str) -> Result<KeyOrigin, DescriptorError> {
+    let (fingerprint_str, path_str) = origin
+        .split_once('/')
+        .ok_or(DescriptorError::Invalid("key origin missing derivation path"))?;
+
+    let fingerprint_bytes =
+        hex_decode_4(fingerprint_str).ok_or(DescriptorError::Invalid("invalid origin fingerprint"))?;
+    let fingerprint = Fingerprint::from(&fingerprint_bytes[..]);
+
+    let path = path_str
+        .split('/')
+        .map(parse_child_number)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(KeyOrigin { fingerprint, path })
+}
+
+fn parse_child_number(s: &str) -> Result<ChildNumber, DescriptorError> {
+    let hardened = s.ends_with('h') || s.ends_with('H') || s.ends_with('\'');
+    let digits = if hardened { &s[..s.len() - 1] } else { s };
+    let idx: u32 = digits
+        .parse()
+        .map_err(|_| DescriptorError::Invalid("invalid derivation path element"))?;
+
+    Ok(if hardened {
+        ChildNumber::from_hardened_idx(idx).map_err(|_| DescriptorError::Invalid("index out of range"))?
+    } else {
+        ChildNumber::from_normal_idx(idx).map_err(|_| DescriptorError::Invalid("index out of range"))?
+    })
+}
+
+/// Parse `0`, `1` or the combined `<0;1>` multi-path branch syntax.
+fn parse_chains(s: &str) -> Result<Chains, DescriptorError> {
+    if let Some(inner) = s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+        let (receive, change) = inner
+            .split_once(';')
+            .ok_or(DescriptorError::Invalid("invalid multi-path branch syntax"))?;
+        Ok(Chains::ReceiveChange(
+            receive
+                .parse()
+                .map_err(|_| DescriptorError::Invalid("invalid multi-path branch"))?,
+            change
+                .parse()
+                .map_err(|_| DescriptorError::Invalid("invalid multi-path branch"))?,
+        ))
+    } else {
+        Ok(Chains::Single(
+            s.parse()
+                .map_err(|_| DescriptorError::Invalid("invalid branch index"))?,
+        ))
+    }
+}
+
+fn hex_decode_4(s: &str) -> Option<[u8; 4]> {
+    if s.len() != 8 || !s.is_ascii() {
+        return None;
+    }
+    let bytes = s.as_bytes();
+    let mut out = [0u8; 4];
+    for (i, byte) in out.iter_mut().enumerate() {
+        let pair = std::str::from_utf8(&bytes[i * 2..i * 2 + 2]).ok()?;
+        *byte = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP-32 test vector 1 master xpub: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+    const XPUB: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+    // the same key, re-encoded with testnet version bytes (otherwise identical payload).
+    const TPUB: &str = "tpubD6NzVbkrYhZ4XgiXtGrdW5XDAPFCL9h7we1vwNCpn8tGbBcgfVYjXyhWo4E1xkh56hjod1RhGjxbaTLV3X4FyWuejifB9jusQ46QzG87VKp";
+
+    #[test]
+    fn parses_wpkh_with_key_origin() {
+        let desc: Descriptor =
+            format!("wpkh([d34db33f/84h/0h/0h]{}/0/*)", XPUB).parse().unwrap();
+
+        assert_eq!(desc.script_type, ScriptType::P2wpkh);
+        assert!(desc.threshold.is_none());
+
+        let origin = desc.key_origins().next().unwrap().unwrap();
+        assert_eq!(origin.fingerprint, Fingerprint::from(&[0xd3, 0x4d, 0xb3, 0x3f][..]));
+        assert_eq!(
+            origin.path,
+            vec![
+                ChildNumber::from_hardened_idx(84).unwrap(),
+                ChildNumber::from_hardened_idx(0).unwrap(),
+                ChildNumber::from_hardened_idx(0).unwrap(),
+            ]
+        );
+    }
+
+    // regression test for a paren-counting bug: `sh(wsh(sortedmulti(...)))` has three opening
+    // parens to match, and a stray trailing `)` used to stay glued onto the last key, making
+    // every p2sh-wrapped-p2wsh multisig descriptor fail to parse.
+    #[test]
+    fn parses_sh_wsh_sortedmulti() {
+        let desc: Descriptor = format!(
+            "sh(wsh(sortedmulti(2,{0}/0/*,{0}/1/*)))",
+            XPUB
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(desc.script_type, ScriptType::P2shP2wsh);
+        assert_eq!(desc.threshold, Some(2));
+        assert_eq!(desc.key_origins().count(), 2);
+    }
+
+    #[test]
+    fn parses_wsh_sortedmulti() {
+        let desc: Descriptor = format!("wsh(sortedmulti(1,{0}/0/*))", XPUB)
+            .parse()
+            .unwrap();
+
+        assert_eq!(desc.script_type, ScriptType::P2wsh);
+        assert_eq!(desc.threshold, Some(1));
+    }
+
+    #[test]
+    fn sortedmulti_rejects_zero_threshold() {
+        let err = format!("wsh(sortedmulti(0,{0}/0/*))", XPUB).parse::<Descriptor>();
+        assert!(matches!(err, Err(DescriptorError::Invalid(_))));
+    }
+
+    #[test]
+    fn sortedmulti_rejects_threshold_above_key_count() {
+        let err = format!("wsh(sortedmulti(2,{0}/0/*))", XPUB).parse::<Descriptor>();
+        assert!(matches!(err, Err(DescriptorError::Invalid(_))));
+    }
+
+    #[test]
+    fn sortedmulti_rejects_mismatched_cosigner_networks() {
+        let err = format!("wsh(sortedmulti(2,{0}/0/*,{1}/0/*))", XPUB, TPUB).parse::<Descriptor>();
+        assert!(matches!(err, Err(DescriptorError::Invalid(_))));
+    }
+
+    #[test]
+    fn descriptor_matches_network_folds_testnet_into_regtest_and_signet() {
+        let desc: Descriptor = format!("wpkh({}/0/*)", TPUB).parse().unwrap();
+
+        assert!(desc.matches_network(Network::Testnet));
+        assert!(desc.matches_network(Network::Regtest));
+        assert!(desc.matches_network(Network::Signet));
+        assert!(!desc.matches_network(Network::Bitcoin));
+    }
+
+    // regression test: an origin fingerprint containing a multi-byte UTF-8 character has the
+    // right byte length (8) but no 2-byte boundary lines up with its char boundaries, so a
+    // naive `&s[i*2..i*2+2]` slice panics instead of failing to parse.
+    #[test]
+    fn parse_origin_rejects_non_ascii_fingerprint_instead_of_panicking() {
+        let err = format!("wpkh([1世1234/84h/0h/0h]{}/0/*)", XPUB).parse::<Descriptor>();
+        assert!(matches!(err, Err(DescriptorError::Invalid(_))));
+    }
+}
@@ -0,0 +1,20 @@
+use bitcoin_hashes::{hash_newtype, sha256};
+
+hash_newtype!(
+    ScriptHash,
+    sha256::Hash,
+    32,
+    doc = "The electrum-style scripthash used to subscribe for address activity"
+);
+
+/// The script types bwt knows how to derive addresses for, as indicated by the
+/// version bytes of the extended pubkey (or the descriptor function) it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    P2pkh,
+    P2wpkh,
+    P2shP2wpkh,
+    P2tr,
+    P2wsh,
+    P2shP2wsh,
+}